@@ -0,0 +1,143 @@
+use std::ffi::CString;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use windows::core::{HSTRING, PCSTR, PCWSTR, PWSTR};
+use windows::Win32::Foundation::{HLOCAL, LocalFree};
+use windows::Win32::Security::Authorization::ConvertStringSidToSidA;
+use windows::Win32::Security::{
+    LookupAccountSidW, PSID, SID_NAME_USE, SidTypeAlias, SidTypeGroup, SidTypeWellKnownGroup,
+    SidTypeUser,
+};
+
+/// Process-wide cache of resolved accounts, keyed by `(computer, SID)`, so
+/// repeated lookups of the same SID (shared/group SIDs, re-runs over the
+/// same list) don't re-hit `LookupAccountSidW`.
+static ACCOUNT_CACHE: Lazy<Mutex<Vec<(String, Account)>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+fn cache_key(sid_str: &str, computer: Option<&str>) -> String {
+    match computer {
+        Some(host) => format!("{host}\\{sid_str}"),
+        None => sid_str.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub enum AccountKind {
+    User,
+    Group,
+    Alias,
+    WellKnownGroup,
+    Unknown,
+}
+
+impl From<SID_NAME_USE> for AccountKind {
+    fn from(value: SID_NAME_USE) -> Self {
+        match value {
+            SidTypeUser => AccountKind::User,
+            SidTypeGroup => AccountKind::Group,
+            SidTypeAlias => AccountKind::Alias,
+            SidTypeWellKnownGroup => AccountKind::WellKnownGroup,
+            _ => AccountKind::Unknown,
+        }
+    }
+}
+
+impl std::fmt::Display for AccountKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            AccountKind::User => "User",
+            AccountKind::Group => "Group",
+            AccountKind::Alias => "Alias",
+            AccountKind::WellKnownGroup => "WellKnownGroup",
+            AccountKind::Unknown => "Unknown",
+        };
+        f.write_str(label)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub sid: String,
+    pub name: String,
+    pub domain: String,
+    pub kind: AccountKind,
+}
+
+/// Owned SID buffer allocated by `ConvertStringSidToSidA`; frees itself via
+/// `LocalFree` on drop.
+struct OwnedSid {
+    inner: PSID,
+}
+
+impl Drop for OwnedSid {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = LocalFree(HLOCAL(self.inner.0));
+        }
+    }
+}
+
+/// Resolves a SID string to an [`Account`], using the process-wide cache
+/// before falling back to `LookupAccountSidW`. When `computer` is `Some`,
+/// the lookup (and cache entry) is scoped to that remote host's SAM/domain.
+pub fn get_accountname_by_sid(sid_str: &str, computer: Option<&str>) -> Result<Account> {
+    let key = cache_key(sid_str, computer);
+    {
+        let cache = ACCOUNT_CACHE.lock().unwrap();
+        if let Some((_, account)) = cache.iter().find(|(k, _)| *k == key) {
+            return Ok(account.clone());
+        }
+    }
+
+    let account = lookup_account_by_sid(sid_str, computer)?;
+    ACCOUNT_CACHE.lock().unwrap().push((key, account.clone()));
+    Ok(account)
+}
+
+fn lookup_account_by_sid(sid_str: &str, computer: Option<&str>) -> Result<Account> {
+    let sid_c_string = CString::new(sid_str)?;
+    let mut sid_ptr = OwnedSid {
+        inner: PSID::default(),
+    };
+
+    unsafe {
+        ConvertStringSidToSidA(
+            PCSTR::from_raw(sid_c_string.as_ptr() as *const u8),
+            &mut sid_ptr.inner,
+        )?;
+    }
+
+    let mut name: [u16; 256] = [0; 256];
+    let mut name_size = name.len() as u32;
+    let name_pwstr = PWSTR::from_raw(name.as_mut_ptr());
+    let mut domain_name: [u16; 256] = [0; 256];
+    let mut domain_name_size = domain_name.len() as u32;
+    let domain_name_pwstr = PWSTR::from_raw(domain_name.as_mut_ptr());
+    let mut sid_name_use = SID_NAME_USE::default();
+
+    let system_name = computer.map(|host| HSTRING::from(format!("\\\\{host}")));
+    let system_name_pcwstr = system_name
+        .as_ref()
+        .map_or(PCWSTR::null(), |h| PCWSTR::from_raw(h.as_ptr()));
+
+    unsafe {
+        LookupAccountSidW(
+            system_name_pcwstr,
+            sid_ptr.inner,
+            name_pwstr,
+            &mut name_size,
+            domain_name_pwstr,
+            &mut domain_name_size,
+            &mut sid_name_use,
+        )?;
+
+        Ok(Account {
+            sid: sid_str.to_string(),
+            name: name_pwstr.to_string()?,
+            domain: domain_name_pwstr.to_string()?,
+            kind: AccountKind::from(sid_name_use),
+        })
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone};
+
+/// Parses a CIM_DATETIME string (`yyyymmddHHMMSS.ffffff±UUU`, as returned by
+/// WMI properties like `Win32_UserProfile.LastUseTime`) into a timestamp.
+pub fn parse_cim_datetime(value: &str) -> Result<DateTime<FixedOffset>> {
+    if value.len() < 25 {
+        return Err(anyhow!("CIM_DATETIME string too short: {value}"));
+    }
+
+    let year: i32 = value[0..4].parse().context("CIM_DATETIME year")?;
+    let month: u32 = value[4..6].parse().context("CIM_DATETIME month")?;
+    let day: u32 = value[6..8].parse().context("CIM_DATETIME day")?;
+    let hour: u32 = value[8..10].parse().context("CIM_DATETIME hour")?;
+    let minute: u32 = value[10..12].parse().context("CIM_DATETIME minute")?;
+    let second: u32 = value[12..14].parse().context("CIM_DATETIME second")?;
+    let micros: u32 = value[15..21].parse().context("CIM_DATETIME microseconds")?;
+    let sign: i32 = if &value[21..22] == "-" { -1 } else { 1 };
+    let offset_minutes: i32 = value[22..25].parse().context("CIM_DATETIME UTC offset")?;
+
+    let date = NaiveDate::from_ymd_opt(year, month, day)
+        .ok_or_else(|| anyhow!("invalid date in CIM_DATETIME: {value}"))?;
+    let naive = date
+        .and_hms_micro_opt(hour, minute, second, micros)
+        .ok_or_else(|| anyhow!("invalid time in CIM_DATETIME: {value}"))?;
+    let offset = FixedOffset::east_opt(sign * offset_minutes * 60)
+        .ok_or_else(|| anyhow!("invalid UTC offset in CIM_DATETIME: {value}"))?;
+
+    offset
+        .from_local_datetime(&naive)
+        .single()
+        .ok_or_else(|| anyhow!("ambiguous local time in CIM_DATETIME: {value}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, Timelike};
+
+    #[test]
+    fn parses_a_valid_timestamp() {
+        let parsed = parse_cim_datetime("20260115093012.123456+000").unwrap();
+        assert_eq!(parsed.year(), 2026);
+        assert_eq!(parsed.month(), 1);
+        assert_eq!(parsed.day(), 15);
+        assert_eq!(parsed.hour(), 9);
+        assert_eq!(parsed.minute(), 30);
+        assert_eq!(parsed.second(), 12);
+        assert_eq!(parsed.timestamp_subsec_micros(), 123456);
+        assert_eq!(parsed.offset().local_minus_utc(), 0);
+    }
+
+    #[test]
+    fn parses_a_negative_utc_offset() {
+        let parsed = parse_cim_datetime("20250704235959.000000-300").unwrap();
+        assert_eq!(parsed.offset().local_minus_utc(), -300 * 60);
+        assert_eq!(parsed.year(), 2025);
+        assert_eq!(parsed.hour(), 23);
+    }
+
+    #[test]
+    fn rejects_a_string_that_is_too_short() {
+        assert!(parse_cim_datetime("20260115093012.123").is_err());
+    }
+
+    #[test]
+    fn rejects_an_invalid_calendar_date() {
+        assert!(parse_cim_datetime("20261315093012.123456+000").is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use windows::Win32::Security::Credentials::{
+    CRED_FLAGS, CRED_PERSIST_LOCAL_MACHINE, CRED_TYPE_GENERIC, CREDENTIALW, CredFree, CredReadW,
+    CredWriteW,
+};
+use windows::Win32::Foundation::ERROR_NOT_FOUND;
+use windows::core::PCWSTR;
+
+/// A username/password pair pulled from (or destined for) Windows Credential
+/// Manager under the `winprofiledelete:<HOST>` generic credential.
+#[derive(Debug, Clone)]
+pub struct Credential {
+    pub username: String,
+    pub password: String,
+}
+
+fn target_name(host: &str) -> String {
+    format!("winprofiledelete:{host}")
+}
+
+fn wide_null(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// Reads the generic credential stored for `host`, if any. Returns `Ok(None)`
+/// when no credential has been saved, so callers can fall back to prompting.
+pub fn read_credential(host: &str) -> Result<Option<Credential>> {
+    let target = wide_null(&target_name(host));
+    let mut cred_ptr: *mut CREDENTIALW = std::ptr::null_mut();
+
+    unsafe {
+        if let Err(e) = CredReadW(
+            PCWSTR::from_raw(target.as_ptr()),
+            CRED_TYPE_GENERIC,
+            0,
+            &mut cred_ptr,
+        ) {
+            if e.code() == ERROR_NOT_FOUND.to_hresult() {
+                return Ok(None);
+            }
+            return Err(e.into());
+        }
+
+        let cred = &*cred_ptr;
+        let username = if cred.UserName.is_null() {
+            String::new()
+        } else {
+            cred.UserName.to_string()?
+        };
+        let password = if cred.CredentialBlob.is_null() || cred.CredentialBlobSize == 0 {
+            String::new()
+        } else {
+            let blob = std::slice::from_raw_parts(
+                cred.CredentialBlob as *const u16,
+                cred.CredentialBlobSize as usize / 2,
+            );
+            String::from_utf16_lossy(blob)
+        };
+
+        let result = Credential { username, password };
+        let _ = CredFree(cred_ptr as *const _);
+        Ok(Some(result))
+    }
+}
+
+/// Persists `username`/`password` for `host` into Windows Credential Manager
+/// so later non-interactive runs don't need to prompt again.
+pub fn write_credential(host: &str, username: &str, password: &str) -> Result<()> {
+    let mut target = wide_null(&target_name(host));
+    let mut username_wide = wide_null(username);
+    let mut password_blob: Vec<u8> = password
+        .encode_utf16()
+        .flat_map(|unit| unit.to_le_bytes())
+        .collect();
+
+    let credential = CREDENTIALW {
+        Flags: CRED_FLAGS(0),
+        Type: CRED_TYPE_GENERIC,
+        TargetName: windows::core::PWSTR::from_raw(target.as_mut_ptr()),
+        Comment: windows::core::PWSTR::null(),
+        LastWritten: Default::default(),
+        CredentialBlobSize: password_blob.len() as u32,
+        CredentialBlob: password_blob.as_mut_ptr(),
+        Persist: CRED_PERSIST_LOCAL_MACHINE,
+        AttributeCount: 0,
+        Attributes: std::ptr::null_mut(),
+        TargetAlias: windows::core::PWSTR::null(),
+        UserName: windows::core::PWSTR::from_raw(username_wide.as_mut_ptr()),
+    };
+
+    unsafe {
+        CredWriteW(&credential, 0)?;
+    }
+    Ok(())
+}
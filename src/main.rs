@@ -1,20 +1,32 @@
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 
+mod accounts;
+mod cim_datetime;
+mod credentials;
+mod report;
+mod wmi_session;
+
 use std::collections::HashSet;
 use std::ffi::CString;
 use std::io::stdin;
 use anyhow::Result;
-use wmi::{COMLibrary, WMIConnection, WMIError};
-use serde::Deserialize;
+use chrono::{DateTime, FixedOffset, Utc};
+use wmi::{COMLibrary, WMIConnection};
+use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
-use windows::core::{PCSTR, PCWSTR, PWSTR};
-use windows::Win32::Foundation::{LocalFree, HLOCAL};
-use windows::Win32::Security::{LookupAccountSidW, PSID, SID_NAME_USE};
-use windows::Win32::Security::Authorization::ConvertStringSidToSidA;
+use windows::core::PCSTR;
+use windows::Win32::System::Console::{
+    CONSOLE_MODE, ENABLE_ECHO_INPUT, GetConsoleMode, GetStdHandle, STD_INPUT_HANDLE, SetConsoleMode,
+};
 use windows::Win32::UI::Shell::DeleteProfileA;
 
-#[derive(Debug, Clone)]
+use accounts::{Account, AccountKind, get_accountname_by_sid};
+use credentials::Credential;
+use report::{DeleteAction, DeleteResult, OutputFormat};
+use wmi_session::AuthenticatedWmiSession;
+
+#[derive(Debug, Clone, Serialize)]
 pub struct ProfileInfo {
     pub domain: Option<String>,
     pub username: Option<String>,
@@ -24,6 +36,8 @@ pub struct ProfileInfo {
     pub status: u32,
     pub loaded: bool,
     pub size: Option<u64>,
+    pub kind: AccountKind,
+    pub last_use: Option<DateTime<FixedOffset>>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -35,39 +49,25 @@ struct Win32_UserProfile {
     Special: bool,
     LocalPath: String,
     Loaded: bool,
+    LastUseTime: Option<String>,
 }
 
-#[derive(Debug)]
-struct AccountInfo {
-    username: String,
-    domain_name: String,
-}
-
-struct WinPointer {
-    inner: PSID,
-}
-
-impl Drop for WinPointer {
-    fn drop(&mut self) {
-        unsafe {
-            let _ = LocalFree(HLOCAL(self.inner.0));
-        }
-    }
-}
-
-fn get_user_profiles(wmi_con: &WMIConnection) -> Result<Vec<ProfileInfo>, WMIError> {
-    let win32_up: Vec<Win32_UserProfile> = wmi_con.query()?;
+fn get_user_profiles(wmi: &WmiSession, computer: Option<&str>) -> Result<Vec<ProfileInfo>> {
+    let win32_up: Vec<Win32_UserProfile> = wmi.query()?;
     let vec = win32_up
         .iter()
         .filter(|up| !up.Special)
         .filter(|up| up.SID.starts_with("S-1-5-21-"))
         .map(|up| {
-            let account_info = lookup_account_by_sid(&up.SID).ok();
+            let account = get_accountname_by_sid(&up.SID, computer).unwrap_or_else(|_| Account {
+                sid: up.SID.clone(),
+                name: String::new(),
+                domain: String::new(),
+                kind: AccountKind::Unknown,
+            });
             ProfileInfo {
-                domain: account_info.as_ref().map(|a| a.domain_name.clone()),
-                username: account_info
-                    .as_ref()
-                    .map(|account| account.username.clone()),
+                domain: Some(account.domain).filter(|s| !s.is_empty()),
+                username: Some(account.name).filter(|s| !s.is_empty()),
                 sid: up.SID.clone(),
                 health_status: up.HealthStatus,
                 roaming_configured: up.RoamingConfigured,
@@ -78,58 +78,30 @@ fn get_user_profiles(wmi_con: &WMIConnection) -> Result<Vec<ProfileInfo>, WMIErr
                 } else {
                     get_dir_size(&up.LocalPath).ok()
                 },
+                kind: account.kind,
+                last_use: up
+                    .LastUseTime
+                    .as_deref()
+                    .and_then(|s| cim_datetime::parse_cim_datetime(s).ok()),
             }
         })
         .collect();
     Ok(vec)
 }
 
-fn lookup_account_by_sid(sid_str: &str) -> Result<AccountInfo> {
-    let sid_c_string = CString::new(sid_str)?;
-    let mut sid_ptr = WinPointer {
-        inner: PSID::default(),
-    };
-
-    unsafe {
-        ConvertStringSidToSidA(
-            PCSTR::from_raw(sid_c_string.as_ptr() as *const u8),
-            &mut sid_ptr.inner,
-        )?;
-    }
-
-    let mut name: [u16; 256] = [0; 256];
-    let mut name_size = name.len() as u32;
-    let name_pwstr = PWSTR::from_raw(name.as_mut_ptr());
-    let mut domain_name: [u16; 256] = [0; 256];
-    let mut domain_name_size = domain_name.len() as u32;
-    let domain_name_pwstr = PWSTR::from_raw(domain_name.as_mut_ptr());
-    let mut sid_name_use = SID_NAME_USE::default();
-
-    unsafe {
-        LookupAccountSidW(
-            PCWSTR::null(),
-            sid_ptr.inner,
-            name_pwstr,
-            &mut name_size,
-            domain_name_pwstr,
-            &mut domain_name_size,
-            &mut sid_name_use,
-        )?;
-
-        Ok(AccountInfo {
-            username: name_pwstr.to_string()?,
-            domain_name: domain_name_pwstr.to_string()?,
-        })
-    }
-}
-
-fn delete_user_profile(sid_str: &str) -> Result<()> {
+fn delete_user_profile(sid_str: &str, computer: Option<&str>) -> Result<()> {
     let sid_c_string = CString::new(sid_str)?;
+    let computer_c_string = computer
+        .map(|host| CString::new(format!("\\\\{host}")))
+        .transpose()?;
+    let computer_pcstr = computer_c_string
+        .as_ref()
+        .map_or(PCSTR::null(), |c| PCSTR::from_raw(c.as_ptr() as *const u8));
     unsafe {
         DeleteProfileA(
             PCSTR::from_raw(sid_c_string.as_ptr() as *const u8),
             PCSTR::null(),
-            PCSTR::null(),
+            computer_pcstr,
         )?;
     }
     Ok(())
@@ -143,17 +115,235 @@ fn get_dir_size(path: &String) -> Result<u64> {
         .sum())
 }
 
+struct Cli {
+    computer: Option<String>,
+    save_credentials: bool,
+    older_than_days: Option<i64>,
+    larger_than_bytes: Option<u64>,
+    dry_run: bool,
+    format: OutputFormat,
+}
+
+/// Parses `--computer <HOST>`, `--save-credentials`, `--older-than <DAYS>`,
+/// `--larger-than <BYTES>`, `--dry-run` and `--format <table|json>` out of
+/// the process argv.
+fn parse_args() -> Cli {
+    let mut computer = None;
+    let mut save_credentials = false;
+    let mut older_than_days = None;
+    let mut larger_than_bytes = None;
+    let mut dry_run = false;
+    let mut format = OutputFormat::Table;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--computer" => computer = args.next(),
+            "--save-credentials" => save_credentials = true,
+            "--older-than" => older_than_days = args.next().and_then(|v| v.parse().ok()),
+            "--larger-than" => larger_than_bytes = args.next().and_then(|v| v.parse().ok()),
+            "--dry-run" => dry_run = true,
+            "--format" => {
+                format = match args.next().as_deref() {
+                    Some("json") => OutputFormat::Json,
+                    _ => OutputFormat::Table,
+                }
+            }
+            _ => {}
+        }
+    }
+    Cli {
+        computer,
+        save_credentials,
+        older_than_days,
+        larger_than_bytes,
+        dry_run,
+        format,
+    }
+}
+
+/// Whether `profile` should be included in a non-interactive delete set.
+/// With no `--older-than`/`--larger-than` filters, every profile matches
+/// (mirroring "keep nothing" in the interactive flow); with filters, a
+/// profile matches if it satisfies any of the ones that were given.
+fn matches_selection(profile: &ProfileInfo, cli: &Cli, now: DateTime<FixedOffset>) -> bool {
+    let mut has_filter = false;
+    let mut matched = false;
+
+    if let Some(days) = cli.older_than_days {
+        has_filter = true;
+        if let Some(last_use) = profile.last_use {
+            if (now - last_use).num_days() >= days {
+                matched = true;
+            }
+        }
+    }
+    if let Some(min_size) = cli.larger_than_bytes {
+        has_filter = true;
+        if profile.size.is_some_and(|size| size >= min_size) {
+            matched = true;
+        }
+    }
+
+    !has_filter || matched
+}
+
+pub(crate) fn format_last_use(last_use: Option<DateTime<FixedOffset>>) -> String {
+    last_use
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+/// Resolves the credential to authenticate `host` with: reads it from
+/// Windows Credential Manager, prompting for (and optionally persisting)
+/// one on first use if `save_credentials` is set and none is stored yet.
+fn resolve_credential(host: &str, save_credentials: bool) -> Result<Option<Credential>> {
+    if let Some(credential) = credentials::read_credential(host)? {
+        return Ok(Some(credential));
+    }
+    if !save_credentials {
+        return Ok(None);
+    }
+
+    eprintln!("No stored credentials for {host}, enter them now to save for later runs.");
+    eprintln!("Username:");
+    let mut username = String::new();
+    stdin().read_line(&mut username)?;
+    eprintln!("Password:");
+    let username = username.trim().to_string();
+    let password = read_password()?;
+
+    credentials::write_credential(host, &username, &password)?;
+    Ok(Some(Credential { username, password }))
+}
+
+/// Reads a line from stdin with terminal echo disabled, so the password
+/// isn't shown while being typed or left behind in scrollback. Falls back to
+/// a plain read when stdin isn't an interactive console (e.g. piped/redirected
+/// input for scripted credential seeding), since there's no echo to suppress.
+fn read_password() -> Result<String> {
+    let handle = unsafe { GetStdHandle(STD_INPUT_HANDLE)? };
+    let mut mode = CONSOLE_MODE(0);
+    let is_console = unsafe { GetConsoleMode(handle, &mut mode) }.is_ok();
+    if is_console {
+        unsafe { SetConsoleMode(handle, mode & !ENABLE_ECHO_INPUT)? };
+    }
+
+    let mut password = String::new();
+    let read_result = stdin().read_line(&mut password);
+    if is_console {
+        unsafe { SetConsoleMode(handle, mode)? };
+        eprintln!();
+    }
+    read_result?;
+
+    Ok(password.trim().to_string())
+}
+
+/// Either an ambient-authenticated `wmi` crate connection, or one
+/// authenticated with explicit credentials via [`wmi_session`].
+enum WmiSession {
+    Ambient(WMIConnection),
+    Authenticated(AuthenticatedWmiSession),
+}
+
+impl WmiSession {
+    fn query<T: serde::de::DeserializeOwned>(&self) -> Result<Vec<T>> {
+        match self {
+            WmiSession::Ambient(con) => Ok(con.query()?),
+            WmiSession::Authenticated(session) => session.query(),
+        }
+    }
+}
+
+/// Connects to WMI, optionally against a remote `computer`. When `credential`
+/// is given together with `computer`, the connection authenticates as that
+/// user via a direct
+/// `IWbemLocator::ConnectServer` call (see [`wmi_session`]), since `wmi`'s own
+/// `WMIConnection` only supports ambient pass-through authentication. Without
+/// `computer`, any `credential` is ignored and the local connection is ambient.
+///
+/// Note this only covers the WMI inventory query: `lookup_account_by_sid`
+/// (in [`accounts`]) and `delete_user_profile` above still run under the
+/// caller's ambient identity, since `LookupAccountSidW` and `DeleteProfileA`
+/// have no per-call credential parameter of their own — authenticating those
+/// against `computer` as `credential` would mean first establishing an
+/// explicit authenticated session to the host (e.g. via `WNetAddConnection2`),
+/// which is out of scope here.
+fn connect_wmi(
+    com_con: COMLibrary,
+    computer: Option<&str>,
+    credential: Option<&Credential>,
+) -> Result<WmiSession> {
+    match (computer, credential) {
+        (Some(host), Some(credential)) => Ok(WmiSession::Authenticated(
+            AuthenticatedWmiSession::connect(host, credential)?,
+        )),
+        (Some(host), None) => {
+            let namespace = format!("\\\\{host}\\root\\cimv2");
+            Ok(WmiSession::Ambient(WMIConnection::with_namespace_path(
+                &namespace, com_con,
+            )?))
+        }
+        (None, _) => Ok(WmiSession::Ambient(WMIConnection::new(com_con)?)),
+    }
+}
+
+fn delete_profiles(sids: HashSet<String>, computer: Option<&str>) -> Vec<DeleteResult> {
+    sids.into_iter()
+        .map(|sid| match delete_user_profile(&sid, computer) {
+            Ok(()) => DeleteResult {
+                sid,
+                action: DeleteAction::Deleted,
+                error: None,
+            },
+            Err(e) => DeleteResult {
+                sid,
+                action: DeleteAction::Failed,
+                error: Some(e.to_string()),
+            },
+        })
+        .collect()
+}
+
 fn main() -> Result<()> {
+    let cli = parse_args();
+    let credential = match &cli.computer {
+        Some(host) => resolve_credential(host, cli.save_credentials)?,
+        None => None,
+    };
     let com_con = COMLibrary::new()?;
-    let wmi_con = WMIConnection::new(com_con)?;
-    let mut user_profiles = get_user_profiles(&wmi_con)?;
+    let wmi = connect_wmi(com_con, cli.computer.as_deref(), credential.as_ref())?;
+    let mut user_profiles = get_user_profiles(&wmi, cli.computer.as_deref())?;
     user_profiles.sort_by_key(|k| k.username.clone());
 
-    println!("{0: <5} | {1: <48} | {2: <15} | {3: <20} | {4: <7} | {5: <6} | {6}", "ID", "SID", "Domain", "Username", "Roaming", "Loaded", "Size");
-    for (key, profile) in user_profiles.clone().into_iter().enumerate() {
-        println!("{0: <5} | {1: <48} | {2: <15} | {3: <20} | {4: <7} | {5: <6} | {6}", key, profile.sid, profile.domain.unwrap_or_default(), profile.username.unwrap_or_default(), profile.roaming_configured, profile.loaded, profile.size.unwrap_or_default());
+    report::print_inventory(&user_profiles, cli.format);
+
+    let non_interactive = cli.dry_run || cli.older_than_days.is_some() || cli.larger_than_bytes.is_some();
+    if non_interactive {
+        let now = Utc::now().fixed_offset();
+        let (sid_to_delete, skipped, candidates) =
+            report::print_delete_table(&user_profiles, |p| matches_selection(p, &cli, now), cli.format);
+        if cli.dry_run {
+            match cli.format {
+                OutputFormat::Table => {
+                    println!("Dry run: {} profile(s) would be deleted.", sid_to_delete.len())
+                }
+                OutputFormat::Json => {
+                    report::print_json_report(&user_profiles, Some(&candidates), Some(&skipped))
+                }
+            }
+            return Ok(());
+        }
+        let mut results = skipped;
+        results.extend(delete_profiles(sid_to_delete, cli.computer.as_deref()));
+        report::print_delete_results(&results, cli.format);
+        if cli.format == OutputFormat::Json {
+            report::print_json_report(&user_profiles, None, Some(&results));
+        }
+        return Ok(());
     }
-    println!("Enter ID of profiles to keep: (example: 0,5,7,17)");
+
+    eprintln!("Enter ID of profiles to keep: (example: 0,5,7,17)");
     let mut keep = HashSet::new();
     let mut buffer = String::new();
     stdin().read_line(&mut buffer)?;
@@ -163,35 +353,33 @@ fn main() -> Result<()> {
             keep.insert(u);
         }
     });
-    println!();
-    println!();
-    let mut sid_to_delete = HashSet::new();
-    println!("=== Profiles to delete ===");
-    println!("{0: <48} | {1: <15} | {2: <20} | {3: <7} | {4: <6} | {5}", "SID", "Domain", "Username", "Roaming", "Loaded", "Size");
-    for (key, profile) in user_profiles.clone().into_iter().enumerate() {
-        if !keep.contains(&key) {
-            if profile.loaded {
-                println!("{} can't be deleted, because profile is loaded", profile.sid.clone());
-                continue;
-            }
-            sid_to_delete.insert(profile.sid.clone());
-            println!("{0: <48} | {1: <15} | {2: <20} | {3: <7} | {4: <6} | {5}", profile.sid, profile.domain.unwrap_or_default(), profile.username.unwrap_or_default(), profile.roaming_configured, profile.loaded, profile.size.unwrap_or_default());
-        }
-    }
-    println!("Do you want to continue? (y/n)");
+    eprintln!();
+    eprintln!();
+    let keep_sids: HashSet<String> = user_profiles
+        .iter()
+        .enumerate()
+        .filter(|(key, _)| keep.contains(key))
+        .map(|(_, p)| p.sid.clone())
+        .collect();
+    let (sid_to_delete, skipped, candidates) =
+        report::print_delete_table(&user_profiles, |p| !keep_sids.contains(&p.sid), cli.format);
+    eprintln!("Do you want to continue? (y/n)");
     let mut buffer = String::new();
     stdin().read_line(&mut buffer)?;
     if buffer.trim().to_lowercase() == "y" {
-        for sid in sid_to_delete {
-            let result = delete_user_profile(&sid);
-            if let Ok(_) = result {
-                println!("Deleted profile {}", sid);
-            } else {
-                println!("Failed to delete profile {}", sid);
-            }
+        let mut results = skipped;
+        results.extend(delete_profiles(sid_to_delete, cli.computer.as_deref()));
+        report::print_delete_results(&results, cli.format);
+        if cli.format == OutputFormat::Json {
+            report::print_json_report(&user_profiles, None, Some(&results));
         }
     } else {
-        println!("Aborting!");
+        match cli.format {
+            OutputFormat::Table => println!("Aborting!"),
+            OutputFormat::Json => {
+                report::print_json_report(&user_profiles, Some(&candidates), Some(&skipped))
+            }
+        }
     }
     Ok(())
 }
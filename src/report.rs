@@ -0,0 +1,136 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::{ProfileInfo, format_last_use};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteAction {
+    Deleted,
+    SkippedLoaded,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeleteResult {
+    pub sid: String,
+    pub action: DeleteAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The single JSON document emitted for a `--format json` invocation, so a
+/// consumer always sees exactly one top-level value instead of several
+/// concatenated arrays.
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    profiles: &'a [ProfileInfo],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to_delete: Option<&'a [ProfileInfo]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    results: Option<&'a [DeleteResult]>,
+}
+
+/// Prints one `--format json` document combining the inventory with
+/// whichever of `to_delete`/`results` apply to this invocation.
+pub fn print_json_report(
+    profiles: &[ProfileInfo],
+    to_delete: Option<&[ProfileInfo]>,
+    results: Option<&[DeleteResult]>,
+) {
+    let report = JsonReport {
+        profiles,
+        to_delete,
+        results,
+    };
+    if let Ok(json) = serde_json::to_string_pretty(&report) {
+        println!("{json}");
+    }
+}
+
+/// Prints the full profile inventory as the fixed-width `ID` table. A no-op
+/// in `--format json`, where the caller folds `profiles` into a single
+/// [`print_json_report`] call instead.
+pub fn print_inventory(profiles: &[ProfileInfo], format: OutputFormat) {
+    if format != OutputFormat::Table {
+        return;
+    }
+    println!("{0: <5} | {1: <48} | {2: <15} | {3: <20} | {4: <14} | {5: <7} | {6: <6} | {7: <20} | {8}", "ID", "SID", "Domain", "Username", "Kind", "Roaming", "Loaded", "Last Use", "Size");
+    for (key, profile) in profiles.iter().enumerate() {
+        println!("{0: <5} | {1: <48} | {2: <15} | {3: <20} | {4: <14} | {5: <7} | {6: <6} | {7: <20} | {8}", key, profile.sid, profile.domain.clone().unwrap_or_default(), profile.username.clone().unwrap_or_default(), profile.kind, profile.roaming_configured, profile.loaded, format_last_use(profile.last_use), profile.size.unwrap_or_default());
+    }
+}
+
+/// Computes the "profiles to delete" set: the SIDs that are safe to delete,
+/// a [`DeleteResult`] for each profile skipped because it's loaded, and the
+/// candidate `ProfileInfo`s themselves (for JSON reporting). In
+/// `OutputFormat::Table` this also prints the table immediately; in
+/// `OutputFormat::Json` the caller folds the candidates into a single
+/// [`print_json_report`] call instead.
+pub fn print_delete_table(
+    profiles: &[ProfileInfo],
+    should_delete: impl Fn(&ProfileInfo) -> bool,
+    format: OutputFormat,
+) -> (HashSet<String>, Vec<DeleteResult>, Vec<ProfileInfo>) {
+    let mut sid_to_delete = HashSet::new();
+    let mut candidates = Vec::new();
+    let mut skipped_loaded = Vec::new();
+
+    for profile in profiles {
+        if !should_delete(profile) {
+            continue;
+        }
+        if profile.loaded {
+            skipped_loaded.push(profile.sid.clone());
+            continue;
+        }
+        sid_to_delete.insert(profile.sid.clone());
+        candidates.push(profile.clone());
+    }
+
+    if format == OutputFormat::Table {
+        println!("=== Profiles to delete ===");
+        println!("{0: <48} | {1: <15} | {2: <20} | {3: <14} | {4: <7} | {5: <6} | {6: <20} | {7}", "SID", "Domain", "Username", "Kind", "Roaming", "Loaded", "Last Use", "Size");
+        for sid in &skipped_loaded {
+            println!("{sid} can't be deleted, because profile is loaded");
+        }
+        for profile in &candidates {
+            println!("{0: <48} | {1: <15} | {2: <20} | {3: <14} | {4: <7} | {5: <6} | {6: <20} | {7}", profile.sid, profile.domain.clone().unwrap_or_default(), profile.username.clone().unwrap_or_default(), profile.kind, profile.roaming_configured, profile.loaded, format_last_use(profile.last_use), profile.size.unwrap_or_default());
+        }
+    }
+
+    let skipped_results = skipped_loaded
+        .into_iter()
+        .map(|sid| DeleteResult {
+            sid,
+            action: DeleteAction::SkippedLoaded,
+            error: None,
+        })
+        .collect();
+
+    (sid_to_delete, skipped_results, candidates)
+}
+
+/// Prints the outcome of a deletion pass as plain status lines. A no-op in
+/// `--format json`, where the caller folds `results` into a single
+/// [`print_json_report`] call instead (`skipped_loaded` entries are silent
+/// here either way, since they were already reported by `print_delete_table`).
+pub fn print_delete_results(results: &[DeleteResult], format: OutputFormat) {
+    if format != OutputFormat::Table {
+        return;
+    }
+    for result in results {
+        match result.action {
+            DeleteAction::Deleted => println!("Deleted profile {}", result.sid),
+            DeleteAction::Failed => println!("Failed to delete profile {}", result.sid),
+            DeleteAction::SkippedLoaded => {}
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::ffi::c_void;
+
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use windows::core::BSTR;
+use windows::Win32::System::Com::{
+    CoCreateInstance, CoSetProxyBlanket, CLSCTX_INPROC_SERVER, COAUTHIDENTITY, EOAC_NONE,
+    RPC_C_AUTHN_LEVEL_PKT_PRIVACY, RPC_C_IMP_LEVEL_IMPERSONATE,
+};
+use windows::Win32::System::Rpc::{
+    RPC_C_AUTHN_WINNT, RPC_C_AUTHZ_NONE, SEC_WINNT_AUTH_IDENTITY_UNICODE,
+};
+use windows::Win32::System::Wmi::{
+    IWbemLocator, IWbemServices, WbemLocator, WBEM_FLAG_CONNECT_USE_MAX_WAIT,
+    WBEM_FLAG_FORWARD_ONLY, WBEM_FLAG_RETURN_IMMEDIATELY, WBEM_INFINITE,
+};
+use wmi::de::wbem_class_de::from_wbem_class_obj;
+use wmi::result_enumerator::IWbemClassWrapper;
+
+use crate::credentials::Credential;
+
+/// A WMI connection to a remote host authenticated with an explicit
+/// [`Credential`], built directly on `IWbemLocator::ConnectServer` and
+/// `CoSetProxyBlanket`. `wmi::WMIConnection` has no credentialed-connection
+/// API (it always calls `ConnectServer` with empty user/password/locale), so
+/// a connection that needs to authenticate as someone other than the caller
+/// has to be assembled from the underlying `windows` crate calls instead.
+pub struct AuthenticatedWmiSession {
+    svc: IWbemServices,
+}
+
+impl AuthenticatedWmiSession {
+    pub fn connect(host: &str, credential: &Credential) -> Result<Self> {
+        let loc: IWbemLocator =
+            unsafe { CoCreateInstance(&WbemLocator, None, CLSCTX_INPROC_SERVER)? };
+        let namespace = BSTR::from(format!("\\\\{host}\\root\\cimv2"));
+
+        let svc: IWbemServices = unsafe {
+            loc.ConnectServer(
+                &namespace,
+                &BSTR::from(credential.username.as_str()),
+                &BSTR::from(credential.password.as_str()),
+                &BSTR::new(),
+                WBEM_FLAG_CONNECT_USE_MAX_WAIT.0,
+                &BSTR::new(),
+                None,
+            )?
+        };
+        bind_identity(&svc, credential)?;
+
+        Ok(Self { svc })
+    }
+
+    /// Runs the same `SELECT * FROM <T>` query `wmi::WMIConnection::query`
+    /// would, deserializing each row into `T` via the `wmi` crate's own
+    /// (public) deserializer, but executed over our own authenticated `svc`.
+    pub fn query<T: DeserializeOwned>(&self) -> Result<Vec<T>> {
+        let query_text = wmi::build_query::<T>(None)?;
+        let query_language = BSTR::from("WQL");
+        let query_bstr = BSTR::from(query_text);
+
+        let enumerator = unsafe {
+            self.svc.ExecQuery(
+                &query_language,
+                &query_bstr,
+                WBEM_FLAG_FORWARD_ONLY | WBEM_FLAG_RETURN_IMMEDIATELY,
+                None,
+            )?
+        };
+
+        let mut rows = Vec::new();
+        loop {
+            let mut objs = [None; 1];
+            let mut returned = 0u32;
+            unsafe {
+                enumerator
+                    .Next(WBEM_INFINITE, &mut objs, &mut returned)
+                    .ok()?;
+            }
+            if returned == 0 {
+                break;
+            }
+            let [obj] = objs;
+            let obj = obj.ok_or_else(|| anyhow!("WMI enumerator returned a null object"))?;
+            rows.push(from_wbem_class_obj(IWbemClassWrapper::new(obj))?);
+        }
+        Ok(rows)
+    }
+}
+
+/// Binds `credential` to every call made through `svc`'s proxy, so the
+/// connection authenticates as that user instead of the caller's ambient
+/// identity. `username` is split on `\` into domain/user, matching the form
+/// Windows Credential Manager entries are usually stored in.
+fn bind_identity(svc: &IWbemServices, credential: &Credential) -> Result<()> {
+    let (domain, user) = match credential.username.split_once('\\') {
+        Some((domain, user)) => (domain.to_string(), user.to_string()),
+        None => (String::new(), credential.username.clone()),
+    };
+    let mut user_wide: Vec<u16> = user.encode_utf16().collect();
+    let mut domain_wide: Vec<u16> = domain.encode_utf16().collect();
+    let mut password_wide: Vec<u16> = credential.password.encode_utf16().collect();
+
+    let identity = COAUTHIDENTITY {
+        User: user_wide.as_mut_ptr(),
+        UserLength: user_wide.len() as u32,
+        Domain: domain_wide.as_mut_ptr(),
+        DomainLength: domain_wide.len() as u32,
+        Password: password_wide.as_mut_ptr(),
+        PasswordLength: password_wide.len() as u32,
+        Flags: SEC_WINNT_AUTH_IDENTITY_UNICODE.0,
+    };
+
+    unsafe {
+        CoSetProxyBlanket(
+            svc,
+            RPC_C_AUTHN_WINNT,
+            RPC_C_AUTHZ_NONE,
+            None,
+            RPC_C_AUTHN_LEVEL_PKT_PRIVACY,
+            RPC_C_IMP_LEVEL_IMPERSONATE,
+            Some(&identity as *const COAUTHIDENTITY as *const c_void),
+            EOAC_NONE,
+        )?;
+    }
+
+    Ok(())
+}